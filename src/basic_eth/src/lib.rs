@@ -6,37 +6,343 @@
 //! and sending Ether in transactions. This module uses the `ic_web3` crate, which is a Rust library for interacting with Ethereum.
 
 use candid::candid_method;
-use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs, TransformContext};
 use ic_cdk_macros::{self, update, query};
 use std::str::FromStr;
 
 use ic_web3::transports::ICHttp;
 use ic_web3::Web3;
+use ic_web3::contract::{Contract, Options};
 use ic_web3::ic::{get_eth_addr, KeyInfo};
 use ic_web3::{
-    ethabi::ethereum_types::U256,
-    types::{Address, TransactionParameters},
+    ethabi::ethereum_types::{H256, U256, U64},
+    types::{Address, Bytes, BlockId, BlockNumber, CallRequest, TransactionParameters},
 };
+use candid::CandidType;
+use serde::Deserialize;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use tiny_keccak::{Hasher, Keccak};
 
 thread_local! {
     static ADDRESS : RefCell<String> = RefCell::new("".to_string());
 }
 
-/// The HTTP URL of an Ethereum node.
+/// The HTTP URL of the default (Sepolia) Ethereum node, used to seed the network registry.
 const URL: &str = "https://eth-sepolia.g.alchemy.com/v2/YPe0Rex7dk40_XbWsn0pdm4UysevzMtq";
-/// The unique identifier for the Ethereum network being used.
+/// The chain id of the default (Sepolia) network, used to seed the network registry.
 const CHAIN_ID: u64 = 11155111;
+/// The block explorer base URL of the default (Sepolia) network, used to seed the network registry.
+const EXPLORER_BASE_URL: &str = "https://sepolia.etherscan.io";
 /// A string constant representing the name of a key, used for cryptographic operations.
 const KEY_NAME: &str = "dfx_test_key";
 
-/// Transforms an HTTP response by clearing its headers.
+/// An RPC endpoint and block explorer for a given Ethereum chain id.
+#[derive(Clone, Debug, CandidType, Deserialize)]
+struct NetworkConfig {
+    chain_id: u64,
+    rpc_url: String,
+    explorer_base_url: String,
+}
+
+thread_local! {
+    static NETWORKS: RefCell<HashMap<u64, NetworkConfig>> = RefCell::new({
+        let mut networks = HashMap::new();
+        networks.insert(CHAIN_ID, NetworkConfig {
+            chain_id: CHAIN_ID,
+            rpc_url: URL.to_string(),
+            explorer_base_url: EXPLORER_BASE_URL.to_string(),
+        });
+        networks
+    });
+    static ACTIVE_CHAIN_ID: RefCell<u64> = RefCell::new(CHAIN_ID);
+}
+
+/// Returns the currently active network's configuration.
+fn active_network() -> NetworkConfig {
+    let chain_id = ACTIVE_CHAIN_ID.with(|id| *id.borrow());
+    NETWORKS.with(|networks| {
+        networks.borrow().get(&chain_id).cloned()
+    }).expect("active network must be present in the registry")
+}
+
+/// Registers (or updates) a network under `chain_id`. Does not make it active; call
+/// [`set_active_network`] for that.
+#[update(name = "add_network")]
+#[candid_method(update, rename = "add_network")]
+fn add_network(chain_id: u64, rpc_url: String, explorer_base_url: String) -> Result<(), String> {
+    NETWORKS.with(|networks| {
+        networks.borrow_mut().insert(chain_id, NetworkConfig { chain_id, rpc_url, explorer_base_url });
+    });
+    Ok(())
+}
+
+/// Switches the canister's active network to a previously-registered `chain_id`.
+#[update(name = "set_active_network")]
+#[candid_method(update, rename = "set_active_network")]
+fn set_active_network(chain_id: u64) -> Result<(), String> {
+    let registered = NETWORKS.with(|networks| networks.borrow().contains_key(&chain_id));
+    if !registered {
+        return Err(format!("chain_id={} is not registered; call add_network first", chain_id));
+    }
+    ACTIVE_CHAIN_ID.with(|id| *id.borrow_mut() = chain_id);
+    Ok(())
+}
+
+/// Lists all registered networks.
+#[query(name = "list_networks")]
+#[candid_method(query, rename = "list_networks")]
+fn list_networks() -> Vec<NetworkConfig> {
+    NETWORKS.with(|networks| networks.borrow().values().cloned().collect())
+}
+
+/// Fixed `max_priority_fee_per_gas` used for every EIP-1559 transaction, in Wei (1.5 gwei).
+/// No network priority-fee estimate is queried.
+const PRIORITY_FEE_FLOOR_WEI: u64 = 1_500_000_000;
+
+/// The standard ERC-20 ABI, restricted to the methods this module calls.
+const ERC20_ABI: &[u8] = br#"[
+    {"constant":true,"inputs":[{"name":"_owner","type":"address"}],"name":"balanceOf","outputs":[{"name":"balance","type":"uint256"}],"payable":false,"stateMutability":"view","type":"function"},
+    {"constant":true,"inputs":[],"name":"decimals","outputs":[{"name":"","type":"uint8"}],"payable":false,"stateMutability":"view","type":"function"},
+    {"constant":true,"inputs":[],"name":"name","outputs":[{"name":"","type":"string"}],"payable":false,"stateMutability":"view","type":"function"},
+    {"constant":true,"inputs":[],"name":"symbol","outputs":[{"name":"","type":"string"}],"payable":false,"stateMutability":"view","type":"function"},
+    {"constant":false,"inputs":[{"name":"_to","type":"address"},{"name":"_value","type":"uint256"}],"name":"transfer","outputs":[{"name":"","type":"bool"}],"payable":false,"stateMutability":"nonpayable","type":"function"}
+]"#;
+
+/// Metadata describing an ERC-20 token, returned by [`get_token_metadata`].
+#[derive(CandidType, Deserialize, Debug)]
+struct TokenMetadata {
+    name: String,
+    symbol: String,
+    decimals: u8,
+}
+
+/// 4-byte selector for ERC-20 `transfer(address,uint256)`.
+const SELECTOR_TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// 4-byte selector for ERC-20 `approve(address,uint256)`.
+const SELECTOR_APPROVE: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+/// 4-byte selector for ERC-20 `transferFrom(address,address,uint256)`.
+const SELECTOR_TRANSFER_FROM: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// A single transaction from a scanned block, classified as a plain ETH transfer or an ERC-20 call.
+#[derive(CandidType, Deserialize, Debug)]
+struct TxActivity {
+    from: String,
+    to: String,
+    method: String,
+    token_symbol: Option<String>,
+    value: String,
+}
+
+/// Address of the ENS registry, which maps a name's node to its resolver.
+const ENS_REGISTRY_ADDRESS: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+/// ABI fragment for the ENS registry's `resolver(bytes32)`.
+const ENS_REGISTRY_ABI: &[u8] = br#"[
+    {"constant":true,"inputs":[{"name":"node","type":"bytes32"}],"name":"resolver","outputs":[{"name":"","type":"address"}],"payable":false,"stateMutability":"view","type":"function"}
+]"#;
+/// ABI fragment for an ENS resolver's `addr(bytes32)`.
+const ENS_RESOLVER_ABI: &[u8] = br#"[
+    {"constant":true,"inputs":[{"name":"node","type":"bytes32"}],"name":"addr","outputs":[{"name":"","type":"address"}],"payable":false,"stateMutability":"view","type":"function"}
+]"#;
+
+/// Hashes `data` with Keccak-256, as used throughout Ethereum (including ENS namehashing).
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(data);
+    hasher.finalize(&mut output);
+    output
+}
+
+/// Computes the ENS namehash of a dotted name, per EIP-137: recursively
+/// `keccak256(namehash(parent) ++ keccak256(label))`, starting from 32 zero bytes for the root.
+fn namehash(name: &str) -> [u8; 32] {
+    let mut node = [0u8; 32];
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let label_hash = keccak256(label.as_bytes());
+        let mut buf = [0u8; 64];
+        buf[0..32].copy_from_slice(&node);
+        buf[32..64].copy_from_slice(&label_hash);
+        node = keccak256(&buf);
+    }
+    node
+}
+
+/// Resolves an ENS `name` (e.g. `vitalik.eth`) to the Ethereum address its resolver reports.
+#[update(name = "resolve_ens")]
+#[candid_method(update, rename = "resolve_ens")]
+async fn resolve_ens(name: String) -> Result<String, String> {
+    let node = H256::from(namehash(&name));
+    let network = active_network();
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::Generic))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let registry_addr = Address::from_str(ENS_REGISTRY_ADDRESS).unwrap();
+    let registry = Contract::from_json(w3.eth(), registry_addr, ENS_REGISTRY_ABI)
+        .map_err(|e| format!("invalid ens registry abi: {}", e))?;
+    let resolver_addr: Address = registry.query("resolver", (node,), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("resolve '{}' failed: {}", name, e))?;
+    if resolver_addr == Address::zero() {
+        return Err(format!("'{}' has no resolver set", name));
+    }
+    let resolver = Contract::from_json(w3.eth(), resolver_addr, ENS_RESOLVER_ABI)
+        .map_err(|e| format!("invalid ens resolver abi: {}", e))?;
+    let addr: Address = resolver.query("addr", (node,), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("resolve '{}' addr failed: {}", name, e))?;
+    Ok(format!("0x{}", hex::encode(addr)))
+}
+
+/// Resolves `input` through ENS if it's a `.eth` name, otherwise returns it unchanged.
+async fn resolve_address(input: String) -> Result<String, String> {
+    if input.ends_with(".eth") {
+        resolve_ens(input).await
+    } else {
+        Ok(input)
+    }
+}
+
+/// Identifies the kind of JSON-RPC call an outbound HTTP request carries, so `transform` can
+/// canonicalize its response the right way. Threaded through as the single context byte on the
+/// request's `TransformContext` and read back out of `TransformArgs::context`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RequestKind {
+    Balance = 0,
+    GasPrice = 1,
+    Nonce = 2,
+    SendRawTx = 3,
+    Generic = 4,
+}
+
+impl RequestKind {
+    fn from_byte(b: u8) -> Self {
+        match b {
+            0 => RequestKind::Balance,
+            1 => RequestKind::GasPrice,
+            2 => RequestKind::Nonce,
+            3 => RequestKind::SendRawTx,
+            _ => RequestKind::Generic,
+        }
+    }
+}
+
+/// Builds the `TransformContext` to pass as the second argument of `ICHttp::new`, tagging the
+/// transport's requests with `kind` so the shared `transform` query can dispatch on it.
+fn transform_context(kind: RequestKind) -> TransformContext {
+    TransformContext::from_name("transform".to_string(), vec![kind as u8])
+}
+
+/// Transforms an HTTP response so every replica produces byte-identical output: clears headers
+/// unconditionally, then canonicalizes the JSON-RPC body according to the call type tagged via
+/// [`transform_context`], dropping fields (e.g. provider-specific error `data`) that vary by node.
 #[query(name = "transform")]
 #[candid_method(query, rename = "transform")]
 fn transform(response: TransformArgs) -> HttpResponse {
     let mut t = response.response;
     t.headers = vec![];
-    t 
+    let kind = response.context.first().copied().map(RequestKind::from_byte).unwrap_or(RequestKind::Generic);
+    canonicalize_body(&mut t, kind);
+    t
+}
+
+/// Canonicalizes a JSON-RPC response body in place. Beyond the unconditional header-clearing
+/// done by the caller, this drops nondeterministic sub-fields (e.g. a provider's debug `data` on
+/// an error object) so nodes that hit different providers still agree on the response bytes.
+fn canonicalize_body(response: &mut HttpResponse, kind: RequestKind) {
+    let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&response.body) else {
+        return;
+    };
+    if let Some(error) = value.get_mut("error").and_then(|e| e.as_object_mut()) {
+        error.remove("data");
+    }
+    if kind == RequestKind::SendRawTx {
+        // `send_raw_transaction` errors often echo back replica-local details (e.g. which peer
+        // rejected the tx); keep only the stable code/message pair.
+        if let Some(error) = value.get_mut("error").and_then(|e| e.as_object_mut()) {
+            error.retain(|k, _| k == "code" || k == "message");
+        }
+    }
+    if let Ok(bytes) = serde_json::to_vec(&value) {
+        response.body = bytes;
+    }
+}
+
+thread_local! {
+    /// The next nonce to use per (chain id, address), so rapid successive sends don't collide.
+    static NONCE_CACHE: RefCell<HashMap<(u64, Address), U256>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the next nonce to use for `address` on `chain_id`, seeding the cache from
+/// `eth_getTransactionCount(address, pending)` the first time it's consulted, over its own
+/// `RequestKind::Nonce`-tagged transport so the response is canonicalized as a tx count rather
+/// than riding on the caller's `SendRawTx` transform. Reserves the returned nonce by writing
+/// `n + 1` back into the cache before returning, so two calls that overlap while awaiting gas
+/// estimation/signing/broadcast never hand out the same nonce twice; [`resync_nonce`] undoes the
+/// reservation if the broadcast that was meant to consume it fails.
+async fn next_nonce(rpc_url: &str, chain_id: u64, address: Address) -> Result<U256, String> {
+    if let Some(n) = NONCE_CACHE.with(|cache| cache.borrow().get(&(chain_id, address)).copied()) {
+        NONCE_CACHE.with(|cache| cache.borrow_mut().insert((chain_id, address), n + U256::from(1)));
+        return Ok(n);
+    }
+    let w3 = match ICHttp::new(rpc_url, Some(transform_context(RequestKind::Nonce))) {
+        Ok(v) => Web3::new(v),
+        Err(e) => return Err(e.to_string()),
+    };
+    let n = w3.eth()
+        .transaction_count(address, Some(BlockNumber::Pending))
+        .await
+        .map_err(|e| format!("get tx count error: {}", e))?;
+    NONCE_CACHE.with(|cache| cache.borrow_mut().insert((chain_id, address), n + U256::from(1)));
+    Ok(n)
+}
+
+/// Undoes a nonce reservation after its broadcast fails: removes the cached entry for
+/// `address` on `chain_id` only if it still holds exactly `reserved_next` (the `n + 1` that
+/// reservation's [`next_nonce`] call wrote). This compare-and-remove keeps the cache intact if a
+/// different, still in-flight send has since reserved a later nonce — an unconditional delete
+/// would drop that reservation too and hand its nonce out again on the next [`next_nonce`] call.
+fn resync_nonce(chain_id: u64, address: Address, reserved_next: U256) {
+    NONCE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.get(&(chain_id, address)) == Some(&reserved_next) {
+            cache.remove(&(chain_id, address));
+        }
+    });
+}
+
+/// Estimates the gas limit for a call via `eth_estimateGas`, rather than assuming the plain
+/// 21000 transfer limit, so the gas oracle also works for contract calls (e.g. ERC-20 transfers).
+/// Uses its own `RequestKind::Generic`-tagged transport, since this is a plain read unrelated to
+/// whatever the caller's own transport happens to be tagged for.
+async fn estimate_gas(rpc_url: &str, from: Address, to: Address, value: U256, data: Vec<u8>) -> Result<U256, String> {
+    let w3 = match ICHttp::new(rpc_url, Some(transform_context(RequestKind::Generic))) {
+        Ok(v) => Web3::new(v),
+        Err(e) => return Err(e.to_string()),
+    };
+    let call = CallRequest {
+        from: Some(from),
+        to: Some(to),
+        value: Some(value),
+        data: Some(Bytes(data)),
+        ..Default::default()
+    };
+    w3.eth().estimate_gas(call, None).await.map_err(|e| format!("estimate gas failed: {}", e))
+}
+
+/// Fetches the network's current legacy `eth_gasPrice`, over its own `RequestKind::GasPrice`-tagged
+/// transport, for use both by [`get_eth_gas_price`] and as the gas oracle's fee source for
+/// [`send_eth_in_ether`]'s legacy (non-EIP-1559) transactions.
+async fn fetch_gas_price(rpc_url: &str) -> Result<U256, String> {
+    let w3 = match ICHttp::new(rpc_url, Some(transform_context(RequestKind::GasPrice))) {
+        Ok(v) => Web3::new(v),
+        Err(e) => return Err(e.to_string()),
+    };
+    w3.eth().gas_price().await.map_err(|e| format!("get gas price failed: {}", e))
 }
 
 /// Fetches the current gas price from the Ethereum network.
@@ -44,11 +350,8 @@ fn transform(response: TransformArgs) -> HttpResponse {
 #[update(name = "get_eth_gas_price")]
 #[candid_method(update, rename = "get_eth_gas_price")]
 async fn get_eth_gas_price() -> Result<String, String> {
-    let w3 = match ICHttp::new(URL, None) {
-        Ok(v) => { Web3::new(v) },
-        Err(e) => { return Err(e.to_string()) },
-    };
-    let gas_price = w3.eth().gas_price().await.map_err(|e| format!("get gas price failed: {}", e))?;
+    let network = active_network();
+    let gas_price = fetch_gas_price(&network.rpc_url).await?;
     ic_cdk::println!("gas price: {}", gas_price);
     Ok(format!("{} WEI", gas_price))
 }
@@ -78,7 +381,8 @@ async fn get_eth_address() -> Result<String,String> {
 #[candid_method(update, rename = "get_eth_balance")]
 async fn get_eth_balance() -> Result<String, String> {
     let addr = get_eth_address().await.map_err(|e| format!("get eth address failed: {}", e))?;
-    let w3 = match ICHttp::new(URL, None) {
+    let network = active_network();
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::Balance))) {
         Ok(v) => { Web3::new(v) },
         Err(e) => { return Err(e.to_string()) },
     };
@@ -97,57 +401,350 @@ async fn eth_to_wei(eth: f64) -> Result<String, String> {
     Ok(format!("{}",wei as u64))
 }
 
-/// Sends Ether to another Ethereum address.
+/// Sends Ether to another Ethereum address using a legacy (non-EIP-1559) transaction.
 /// It constructs and signs a transaction, then sends it to the Ethereum network.
 /// This function demonstrates how to create and send transactions on Ethereum.
+/// When `nonce`/`gas` are `None` they're filled in automatically by the nonce manager and gas
+/// oracle; `gas_price` is always the network's current `eth_gasPrice`, not a hardcoded value.
 #[update(name = "send_eth_in_ether")]
 #[candid_method(update, rename = "send_eth_in_ether")]
-async fn send_eth_in_ether(to: String, eth_value: f64, nonce: Option<u64>) -> Result<String, String> {
+async fn send_eth_in_ether(to: String, eth_value: f64, nonce: Option<u64>, gas: Option<u64>) -> Result<String, String> {
     if eth_value <= f64::from(0){
         return Err(format!("value={} can only be a positive number", eth_value))
     }
-    let value = (eth_value * 1e18) as u64;
+    // Scale through a decimal string rather than `as u64`: values above ~18.44 ETH overflow u64
+    // and a float-to-int cast silently saturates instead of erroring, broadcasting a tx for a
+    // wildly different amount than requested.
+    let value = U256::from_dec_str(&format!("{:.0}", eth_value * 1e18))
+        .map_err(|e| format!("eth_value={} is not a valid wei amount: {}", eth_value, e))?;
+    let to = resolve_address(to).await?;
     let to = Address::from_str(&to).map_err(|e| format!("to='{}' is not a valid ethereum address. Error={}", to, e))?;
     let derivation_path = vec![ic_cdk::id().as_slice().to_vec()];
     let key_info = KeyInfo{ derivation_path: derivation_path, key_name: KEY_NAME.to_string(), ecdsa_sign_cycles: None };
     let from_addr = get_eth_addr(None, None, KEY_NAME.to_string())
         .await
         .map_err(|e| format!("get canister eth addr failed: {}", e))?;
-    let w3 = match ICHttp::new(URL, None) {
+    let network = active_network();
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::SendRawTx))) {
         Ok(v) => { Web3::new(v) },
         Err(e) => { return Err(e.to_string()) },
     };
-    let tx_count: U256 = if let Some(count) = nonce {
-        count.into() 
-    } else {
-        let v = w3.eth()
-            .transaction_count(from_addr, None)
-            .await
-            .map_err(|e| format!("get tx count error: {}", e))?;
-        v
+    let tx_count: U256 = match nonce {
+        Some(count) => count.into(),
+        None => next_nonce(&network.rpc_url, network.chain_id, from_addr).await?,
+    };
+    let gas_limit: U256 = match gas {
+        Some(g) => g.into(),
+        None => estimate_gas(&network.rpc_url, from_addr, to, value, vec![]).await?,
     };
-        
+    let gas_price = fetch_gas_price(&network.rpc_url).await?;
+
     ic_cdk::println!("canister eth address {} tx count: {}", hex::encode(from_addr), tx_count);
     let tx = TransactionParameters {
         to: Some(to),
         nonce: Some(tx_count),
-        value: U256::from(value),
-        gas_price: Some(U256::from(100_000_000_000u64)),
-        gas: U256::from(21000),
+        value,
+        gas_price: Some(gas_price),
+        gas: gas_limit,
         ..Default::default()
     };
     let signed_tx = w3.accounts()
-        .sign_transaction(tx, hex::encode(from_addr), key_info, CHAIN_ID)
+        .sign_transaction(tx, hex::encode(from_addr), key_info, network.chain_id)
         .await
         .map_err(|e| format!("sign tx error: {}", e))?;
     match w3.eth().send_raw_transaction(signed_tx.raw_transaction).await {
-        Ok(txhash) => { 
+        Ok(txhash) => {
             ic_cdk::println!("txhash: 0x{}", hex::encode(txhash.0));
-            Ok(format!("https://sepolia.etherscan.io/tx/0x{}", hex::encode(txhash.0)))
+            Ok(format!("{}/tx/0x{}", network.explorer_base_url, hex::encode(txhash.0)))
+        },
+        Err(e) => {
+            if nonce.is_none() {
+                resync_nonce(network.chain_id, from_addr, tx_count + U256::from(1));
+            }
+            Err(format!("Error:{}", e))
         },
-        Err(e) => { Err(format!("Error:{}", e)) },
     }
-    
+
+}
+
+/// Sends Ether to another Ethereum address using an EIP-1559 dynamic-fee transaction.
+/// Fetches the latest block's `base_fee_per_gas`, sets `max_fee_per_gas` to twice that plus the
+/// fixed [`PRIORITY_FEE_FLOOR_WEI`] priority fee, and signs/broadcasts a type-2 transaction
+/// instead of a legacy single-`gas_price` one.
+/// When `nonce`/`gas` are `None` they're filled in automatically by the nonce manager and gas oracle.
+#[update(name = "send_eth_eip1559")]
+#[candid_method(update, rename = "send_eth_eip1559")]
+async fn send_eth_eip1559(to: String, eth_value: f64, nonce: Option<u64>, gas: Option<u64>) -> Result<String, String> {
+    if eth_value <= f64::from(0){
+        return Err(format!("value={} can only be a positive number", eth_value))
+    }
+    // Scale through a decimal string rather than `as u64`: values above ~18.44 ETH overflow u64
+    // and a float-to-int cast silently saturates instead of erroring, broadcasting a tx for a
+    // wildly different amount than requested.
+    let value = U256::from_dec_str(&format!("{:.0}", eth_value * 1e18))
+        .map_err(|e| format!("eth_value={} is not a valid wei amount: {}", eth_value, e))?;
+    let to = resolve_address(to).await?;
+    let to = Address::from_str(&to).map_err(|e| format!("to='{}' is not a valid ethereum address. Error={}", to, e))?;
+    let derivation_path = vec![ic_cdk::id().as_slice().to_vec()];
+    let key_info = KeyInfo{ derivation_path: derivation_path, key_name: KEY_NAME.to_string(), ecdsa_sign_cycles: None };
+    let from_addr = get_eth_addr(None, None, KEY_NAME.to_string())
+        .await
+        .map_err(|e| format!("get canister eth addr failed: {}", e))?;
+    let network = active_network();
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::SendRawTx))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let tx_count: U256 = match nonce {
+        Some(count) => count.into(),
+        None => next_nonce(&network.rpc_url, network.chain_id, from_addr).await?,
+    };
+    let gas_limit: U256 = match gas {
+        Some(g) => g.into(),
+        None => estimate_gas(&network.rpc_url, from_addr, to, value, vec![]).await?,
+    };
+
+    let latest_block = w3.eth()
+        .block(BlockId::Number(BlockNumber::Latest))
+        .await
+        .map_err(|e| format!("get latest block failed: {}", e))?
+        .ok_or_else(|| "latest block not found".to_string())?;
+    let base_fee = latest_block.base_fee_per_gas
+        .ok_or_else(|| "network does not report base_fee_per_gas (pre-EIP-1559?)".to_string())?;
+    let priority_fee = U256::from(PRIORITY_FEE_FLOOR_WEI);
+    let max_fee_per_gas = base_fee * U256::from(2) + priority_fee;
+
+    ic_cdk::println!("canister eth address {} tx count: {}", hex::encode(from_addr), tx_count);
+    let tx = TransactionParameters {
+        to: Some(to),
+        nonce: Some(tx_count),
+        value,
+        gas: gas_limit,
+        transaction_type: Some(U64::from(2)),
+        max_fee_per_gas: Some(max_fee_per_gas),
+        max_priority_fee_per_gas: Some(priority_fee),
+        access_list: Some(vec![]),
+        ..Default::default()
+    };
+    let signed_tx = w3.accounts()
+        .sign_transaction(tx, hex::encode(from_addr), key_info, network.chain_id)
+        .await
+        .map_err(|e| format!("sign tx error: {}", e))?;
+    match w3.eth().send_raw_transaction(signed_tx.raw_transaction).await {
+        Ok(txhash) => {
+            ic_cdk::println!("txhash: 0x{}", hex::encode(txhash.0));
+            Ok(format!("{}/tx/0x{}", network.explorer_base_url, hex::encode(txhash.0)))
+        },
+        Err(e) => {
+            if nonce.is_none() {
+                resync_nonce(network.chain_id, from_addr, tx_count + U256::from(1));
+            }
+            Err(format!("Error:{}", e))
+        },
+    }
+}
+
+/// Builds an ERC-20 `Contract` handle bound to `token` over the given `w3` transport.
+fn erc20_contract(w3: &Web3<ICHttp>, token: &str) -> Result<Contract<ICHttp>, String> {
+    let token_addr = Address::from_str(token).map_err(|e| format!("token='{}' is not a valid ethereum address. Error={}", token, e))?;
+    Contract::from_json(w3.eth(), token_addr, ERC20_ABI).map_err(|e| format!("invalid erc20 abi: {}", e))
+}
+
+/// Retrieves the balance of `owner` (defaults to the canister's own address) in a given ERC-20 token,
+/// scaled down by the token's `decimals()` for human readability.
+#[update(name = "get_token_balance")]
+#[candid_method(update, rename = "get_token_balance")]
+async fn get_token_balance(token: String, owner: Option<String>) -> Result<String, String> {
+    let owner = match owner {
+        Some(addr) => addr,
+        None => get_eth_address().await.map_err(|e| format!("get eth address failed: {}", e))?,
+    };
+    let owner = resolve_address(owner).await?;
+    let owner_addr = Address::from_str(&owner).map_err(|e| format!("owner='{}' is not a valid ethereum address. Error={}", owner, e))?;
+    let network = active_network();
+    // `RequestKind::Generic`, not `Balance`: these are `eth_call`s against the token contract
+    // (same as `get_token_metadata`'s reads), not an `eth_getBalance` on the account itself.
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::Generic))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let contract = erc20_contract(&w3, &token)?;
+    let decimals: u8 = contract.query("decimals", (), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("query decimals failed: {}", e))?;
+    let balance: U256 = contract.query("balanceOf", (owner_addr,), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("query balanceOf failed: {}", e))?;
+    let scaled = balance.to_string().parse::<f64>().unwrap() / 10f64.powi(decimals as i32);
+    Ok(format!("{} (raw {}, decimals {})", scaled, balance, decimals))
+}
+
+/// Retrieves a token's `name`, `symbol` and `decimals`.
+#[update(name = "get_token_metadata")]
+#[candid_method(update, rename = "get_token_metadata")]
+async fn get_token_metadata(token: String) -> Result<TokenMetadata, String> {
+    let network = active_network();
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::Generic))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let contract = erc20_contract(&w3, &token)?;
+    let name: String = contract.query("name", (), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("query name failed: {}", e))?;
+    let symbol: String = contract.query("symbol", (), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("query symbol failed: {}", e))?;
+    let decimals: u8 = contract.query("decimals", (), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("query decimals failed: {}", e))?;
+    Ok(TokenMetadata { name, symbol, decimals })
+}
+
+/// Sends `amount` of an ERC-20 `token` (in the token's own decimal units, e.g. whole tokens) to `to`.
+/// Encodes `transfer(address,uint256)`, signs it with the canister's threshold-ECDSA key, and broadcasts it.
+#[update(name = "send_token")]
+#[candid_method(update, rename = "send_token")]
+async fn send_token(token: String, to: String, amount: f64) -> Result<String, String> {
+    if amount <= f64::from(0) {
+        return Err(format!("amount={} can only be a positive number", amount))
+    }
+    let to = resolve_address(to).await?;
+    let to_addr = Address::from_str(&to).map_err(|e| format!("to='{}' is not a valid ethereum address. Error={}", to, e))?;
+    let derivation_path = vec![ic_cdk::id().as_slice().to_vec()];
+    let key_info = KeyInfo{ derivation_path: derivation_path, key_name: KEY_NAME.to_string(), ecdsa_sign_cycles: None };
+    let from_addr = get_eth_addr(None, None, KEY_NAME.to_string())
+        .await
+        .map_err(|e| format!("get canister eth addr failed: {}", e))?;
+    let network = active_network();
+    // `decimals()` is a plain `eth_call` read, unrelated to the `send_raw_transaction` below, so
+    // it gets its own `RequestKind::Generic`-tagged transport rather than riding on the transfer's.
+    let read_w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::Generic))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let decimals: u8 = erc20_contract(&read_w3, &token)?
+        .query("decimals", (), None, Options::default(), None)
+        .await
+        .map_err(|e| format!("query decimals failed: {}", e))?;
+    // Scale through a decimal string rather than `as u64`: realistic token amounts with 18
+    // decimals (e.g. 100.0) overflow u64 and a float-to-int cast silently saturates instead of
+    // erroring, which would broadcast a transfer for a wildly different amount than requested.
+    let raw_amount = U256::from_dec_str(&format!("{:.0}", amount * 10f64.powi(decimals as i32)))
+        .map_err(|e| format!("amount={} scaled to {} decimals is not a valid raw token amount: {}", amount, decimals, e))?;
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::SendRawTx))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let contract = erc20_contract(&w3, &token)?;
+    let txhash = contract.signed_call(
+        "transfer",
+        (to_addr, raw_amount),
+        Options::default(),
+        hex::encode(from_addr),
+        key_info,
+        network.chain_id,
+    )
+        .await
+        .map_err(|e| format!("send token error: {}", e))?;
+    ic_cdk::println!("token transfer txhash: 0x{}", hex::encode(txhash.0));
+    Ok(format!("{}/tx/0x{}", network.explorer_base_url, hex::encode(txhash.0)))
+}
+
+/// Classifies a single transaction's calldata and, for ERC-20 calls, decodes the recipient/amount
+/// and attempts to resolve the token's `symbol()`.
+async fn classify_tx(w3: &Web3<ICHttp>, tx: &ic_web3::types::Transaction) -> TxActivity {
+    let from = format!("0x{}", hex::encode(tx.from.unwrap_or_default()));
+    let input = &tx.input.0;
+
+    if input.len() < 4 {
+        return TxActivity {
+            from,
+            to: tx.to.map(|a| format!("0x{}", hex::encode(a))).unwrap_or_default(),
+            method: "eth_transfer".to_string(),
+            token_symbol: None,
+            value: tx.value.to_string(),
+        };
+    }
+
+    let selector = [input[0], input[1], input[2], input[3]];
+    let (method, recipient, value) = if selector == SELECTOR_TRANSFER && input.len() >= 4 + 64 {
+        let to = Address::from_slice(&input[16..36]);
+        let value = U256::from_big_endian(&input[36..68]);
+        ("erc20_transfer", format!("0x{}", hex::encode(to)), value.to_string())
+    } else if selector == SELECTOR_TRANSFER_FROM && input.len() >= 4 + 96 {
+        let to = Address::from_slice(&input[48..68]);
+        let value = U256::from_big_endian(&input[68..100]);
+        ("erc20_transfer_from", format!("0x{}", hex::encode(to)), value.to_string())
+    } else if selector == SELECTOR_APPROVE && input.len() >= 4 + 64 {
+        let spender = Address::from_slice(&input[16..36]);
+        let value = U256::from_big_endian(&input[36..68]);
+        ("erc20_approve", format!("0x{}", hex::encode(spender)), value.to_string())
+    } else {
+        return TxActivity {
+            from,
+            to: tx.to.map(|a| format!("0x{}", hex::encode(a))).unwrap_or_default(),
+            method: "eth_transfer".to_string(),
+            token_symbol: None,
+            value: tx.value.to_string(),
+        };
+    };
+
+    let token_symbol = match tx.to.and_then(|addr| erc20_contract(w3, &format!("0x{}", hex::encode(addr))).ok()) {
+        Some(contract) => contract.query("symbol", (), None, Options::default(), None).await.ok(),
+        None => None,
+    };
+
+    TxActivity {
+        from,
+        to: recipient,
+        method: method.to_string(),
+        token_symbol,
+        value,
+    }
+}
+
+/// Fetches a full block (with transactions) and classifies each transaction as a plain ETH
+/// transfer or an ERC-20 interaction by inspecting the first 4 bytes of its `input`.
+#[update(name = "scan_block")]
+#[candid_method(update, rename = "scan_block")]
+async fn scan_block(block_number: u64) -> Result<Vec<TxActivity>, String> {
+    let network = active_network();
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::Generic))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let block = w3.eth()
+        .block_with_txs(BlockId::Number(BlockNumber::Number(block_number.into())))
+        .await
+        .map_err(|e| format!("get block failed: {}", e))?
+        .ok_or_else(|| format!("block {} not found", block_number))?;
+
+    let mut activity = Vec::with_capacity(block.transactions.len());
+    for tx in &block.transactions {
+        activity.push(classify_tx(&w3, tx).await);
+    }
+    Ok(activity)
+}
+
+/// Fetches and classifies the transactions in the latest block. See [`scan_block`].
+#[update(name = "scan_latest_block")]
+#[candid_method(update, rename = "scan_latest_block")]
+async fn scan_latest_block() -> Result<Vec<TxActivity>, String> {
+    let network = active_network();
+    let w3 = match ICHttp::new(&network.rpc_url, Some(transform_context(RequestKind::Generic))) {
+        Ok(v) => { Web3::new(v) },
+        Err(e) => { return Err(e.to_string()) },
+    };
+    let latest_block = w3.eth()
+        .block(BlockId::Number(BlockNumber::Latest))
+        .await
+        .map_err(|e| format!("get latest block failed: {}", e))?
+        .ok_or_else(|| "latest block not found".to_string())?;
+    let block_number = latest_block.number
+        .ok_or_else(|| "latest block has no number yet".to_string())?
+        .as_u64();
+    scan_block(block_number).await
 }
 
 // need this to generate candid